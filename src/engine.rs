@@ -0,0 +1,723 @@
+//Host-agnostic physical model: params, voices, plenum pressure, MIDI handling, and the
+//per-buffer render loop. No cpal/midir here so this module can back both the standalone
+//binary (see `standalone.rs`) and the plugin frontend (see `plugin.rs`).
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rtrb::{Consumer, Producer, RingBuffer};
+use wmidi::MidiMessage;
+
+pub struct RosedaleParams {
+    //Independent wind sections ("ranks"), each with its own static pressure and refill rate
+    pub wind_sections: Vec<WindSection>,
+    //How a sounding note is assigned to one of `wind_sections`
+    pub section_routing: SectionRouting,
+
+    pub valve_flow_rate: f64,
+    pub pulse_duty_cycle: f64,
+    //The sensitivity of the pitch to pressure curve
+    pub pitch_modulation_depth: f64,
+
+    //A meta-parameter for adjusting to abnormal tuning
+    //in reference data
+    pub tuning_multiplier: f64,
+
+    //The point at which the chassis starts absorbing sound waves
+    pub filter_cutoff: f64,
+    //The speed the valves close
+    pub spring_speed: f64,
+
+    //How many semitones a full pitch-bend wheel deflection covers
+    pub pitch_bend_range_semitones: f64,
+
+    //Which oscillator voices the fundamental: the raw pulse, or a sum of sine partials
+    pub oscillator_mode: OscillatorMode,
+    //Relative amplitude of each partial in HARMONIC_NUMBERS, used by the additive oscillator
+    pub partial_gains: [f64; 5],
+
+    //Musical loudness envelope, applied on top of the physical valve_aperature/pressure coupling
+    pub attack_time: f64,
+    pub decay_time: f64,
+    pub sustain_level: f64,
+    pub release_time: f64,
+}
+
+//Selects the waveform generator used in the sample loop; both read the same voice/params state
+#[derive(Clone, Copy, PartialEq)]
+pub enum OscillatorMode {
+    Pulse,
+    Additive,
+}
+
+//A single independent wind supply; voices assigned to it only sag each other's pitch/volume
+#[derive(Clone, Copy)]
+pub struct WindSection {
+    pub max_pressure: f64,
+    pub refill_speed: f64,
+}
+
+//How incoming notes are assigned to a `RosedaleParams::wind_sections` entry
+#[derive(Clone)]
+pub enum SectionRouting {
+    //section = channel % section_count
+    ByChannel,
+    //Ascending split points; a note is assigned to the first section whose split it's >= all of,
+    //e.g. splits [60] makes notes below middle C section 0 and the rest section 1
+    ByKeyboardSplit(Vec<u8>),
+}
+
+//Picks a `wind_sections` index for a newly-triggered note
+fn section_for_note(routing: &SectionRouting, channel: u8, note_index: u8, section_count: usize) -> usize {
+    if section_count == 0 {
+        return 0;
+    }
+    let idx = match routing {
+        SectionRouting::ByChannel => channel as usize % section_count,
+        SectionRouting::ByKeyboardSplit(splits) => {
+            splits.iter().filter(|&&split| note_index >= split).count()
+        }
+    };
+    idx.min(section_count - 1)
+}
+
+impl Default for RosedaleParams {
+    fn default() -> Self {
+        RosedaleParams {
+            wind_sections: vec![WindSection {
+                max_pressure: 1.0,
+                refill_speed: 10.0,
+            }],
+            section_routing: SectionRouting::ByKeyboardSplit(Vec::new()),
+            valve_flow_rate: 0.6,
+            pulse_duty_cycle: 0.3,
+            pitch_modulation_depth: 0.06,
+
+            tuning_multiplier: 1.0,
+
+            filter_cutoff: 1500.0,
+            spring_speed: 25.0,
+
+            pitch_bend_range_semitones: 2.0,
+
+            oscillator_mode: OscillatorMode::Pulse,
+            partial_gains: [1.0, 0.30, 0.15, 0.08, 0.02],
+
+            attack_time: 0.05,
+            decay_time: 0.2,
+            sustain_level: 0.8,
+            release_time: 0.3,
+        }
+    }
+}
+
+//Which RosedaleParams field a CC number is wired to, and the range it's scaled into
+#[derive(Clone, Copy)]
+pub enum CcTarget {
+    //Log-style sweep, good for cutoffs/frequencies
+    FilterCutoffHz(f64, f64),
+    SpringSpeed(f64, f64),
+    MaxPressure(f64, f64),
+    RefillSpeed(f64, f64),
+    PitchModulationDepth(f64, f64),
+}
+
+//The default MIDI CC -> RosedaleParams wiring, loosely following common synth-controller conventions
+const DEFAULT_CC_MAP: &[(u8, CcTarget)] = &[
+    (74, CcTarget::FilterCutoffHz(200.0, 8000.0)),
+    (71, CcTarget::SpringSpeed(1.0, 80.0)),
+    (2, CcTarget::MaxPressure(0.1, 2.0)),
+    (7, CcTarget::RefillSpeed(1.0, 40.0)),
+    (1, CcTarget::PitchModulationDepth(0.0, 0.3)),
+];
+
+//The standard MIDI sustain-pedal CC number, handled separately from the RosedaleParams table
+const SUSTAIN_CC: u8 = 64;
+
+//Scales a raw 0-127 CC value onto [lo, hi], logarithmically for frequency-like targets
+fn scale_cc(value: u8, lo: f64, hi: f64, log_style: bool) -> f64 {
+    let t = value as f64 / 127.0;
+    if log_style {
+        lo * (hi / lo).powf(t)
+    } else {
+        lo + t * (hi - lo)
+    }
+}
+
+fn apply_cc(params: &mut RosedaleParams, target: CcTarget, value: u8) {
+    match target {
+        CcTarget::FilterCutoffHz(lo, hi) => {
+            params.filter_cutoff = scale_cc(value, lo, hi, true);
+        }
+        CcTarget::SpringSpeed(lo, hi) => {
+            params.spring_speed = scale_cc(value, lo, hi, false);
+        }
+        CcTarget::MaxPressure(lo, hi) => {
+            //CC wiring only reaches the primary (first) wind section
+            if let Some(section) = params.wind_sections.get_mut(0) {
+                section.max_pressure = scale_cc(value, lo, hi, false);
+            }
+        }
+        CcTarget::RefillSpeed(lo, hi) => {
+            if let Some(section) = params.wind_sections.get_mut(0) {
+                section.refill_speed = scale_cc(value, lo, hi, false);
+            }
+        }
+        CcTarget::PitchModulationDepth(lo, hi) => {
+            params.pitch_modulation_depth = scale_cc(value, lo, hi, false);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct PlenumPressure {
+    pub pressure: f64,
+}
+
+#[derive(Clone, Copy)]
+pub struct RosedaleVoiceState {
+    phase: f64,
+    sample_history: f64,
+    valve_aperature: f64,
+    opening: bool,
+    attack: f64,
+    freq: f64,
+    //The MIDI channel that triggered this voice, used to look up the channel's pitch-bend
+    channel: u8,
+    //Which wind section (rank) this voice draws pressure from
+    section: usize,
+    //Musical loudness envelope, separate from the physical valve_aperature
+    envelope_stage: EnvelopeStage,
+    envelope_level: f64,
+}
+
+//The ADSR stage a voice's amplitude envelope is in
+#[derive(Clone, Copy, PartialEq)]
+enum EnvelopeStage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+impl RosedaleVoiceState {
+    fn new(note_index: u8) -> Self {
+        let freq = 440.0 * 2.0_f64.powf((note_index as f64 - 69.0) / 12.0);
+
+        Self {
+            phase: 0.00,
+            sample_history: 0.0,
+            valve_aperature: 0.0,
+            opening: false,
+            attack: 0.0,
+            freq: freq,
+            channel: 0,
+            section: 0,
+            envelope_stage: EnvelopeStage::Idle,
+            envelope_level: 0.0,
+        }
+    }
+}
+
+fn calc_dt(sample_rate: f64) -> f64 {
+    1.0 / sample_rate
+}
+
+fn calculate_alpha(cutoff_freq: f64, dt: f64) -> f64 {
+    //The filter coefficient for the plastic organ body
+    let omega_dt = 2.0 * PI * cutoff_freq * dt;
+
+    omega_dt / (1.0 + omega_dt)
+}
+
+//Dont forget to scale the midi velocity to an attack
+fn update_aperature(
+    voice_state: &mut RosedaleVoiceState,
+    pressure: &PlenumPressure,
+    params: &RosedaleParams,
+    dt: f64,
+) {
+    let mut aperature_t = voice_state.valve_aperature;
+
+    if voice_state.opening {
+        const SLOW_OPEN: f64 = 0.08;
+        const FAST_OPEN: f64 = 0.01;
+
+        let valve_speed: f64 = 1.0 / (SLOW_OPEN - (voice_state.attack * (SLOW_OPEN - FAST_OPEN)));
+
+        aperature_t += (valve_speed * dt);
+    } else {
+        let valve_speed = params.spring_speed / (1.0 + (0.875 * pressure.pressure));
+        aperature_t -= (valve_speed * dt);
+    }
+    aperature_t = aperature_t.clamp(0.0, 1.0);
+    voice_state.valve_aperature = aperature_t;
+}
+
+//Drives the musical loudness envelope from `opening`, independent of the valve_aperature model
+fn update_envelope(voice_state: &mut RosedaleVoiceState, params: &RosedaleParams, dt: f64) {
+    if voice_state.opening {
+        if matches!(
+            voice_state.envelope_stage,
+            EnvelopeStage::Idle | EnvelopeStage::Release
+        ) {
+            voice_state.envelope_stage = EnvelopeStage::Attack;
+        }
+    } else if voice_state.envelope_stage != EnvelopeStage::Idle {
+        voice_state.envelope_stage = EnvelopeStage::Release;
+    }
+
+    match voice_state.envelope_stage {
+        EnvelopeStage::Idle => {
+            voice_state.envelope_level = 0.0;
+        }
+        EnvelopeStage::Attack => {
+            //Higher MIDI velocity (voice_state.attack) shortens the attack, same hint as the valve model
+            let attack_time = (params.attack_time * (1.0 - 0.8 * voice_state.attack)).max(0.0001);
+            voice_state.envelope_level += dt / attack_time;
+            if voice_state.envelope_level >= 1.0 {
+                voice_state.envelope_level = 1.0;
+                voice_state.envelope_stage = EnvelopeStage::Decay;
+            }
+        }
+        EnvelopeStage::Decay => {
+            let decay_time = params.decay_time.max(0.0001);
+            voice_state.envelope_level -= dt * (1.0 - params.sustain_level) / decay_time;
+            if voice_state.envelope_level <= params.sustain_level {
+                voice_state.envelope_level = params.sustain_level;
+                voice_state.envelope_stage = EnvelopeStage::Sustain;
+            }
+        }
+        EnvelopeStage::Sustain => {
+            voice_state.envelope_level = params.sustain_level;
+        }
+        EnvelopeStage::Release => {
+            let release_time = params.release_time.max(0.0001);
+            voice_state.envelope_level -= dt / release_time;
+            if voice_state.envelope_level <= 0.0 {
+                voice_state.envelope_level = 0.0;
+                voice_state.envelope_stage = EnvelopeStage::Idle;
+            }
+        }
+    }
+}
+
+fn update_pressure(
+    pressure: &mut PlenumPressure,
+    max_pressure: f64,
+    refill_speed: f64,
+    valve_flow_rate: f64,
+    aperature_area: f64,
+    dt: f64,
+) {
+    let air_in = refill_speed * (max_pressure - pressure.pressure);
+
+    let air_out = valve_flow_rate * aperature_area * pressure.pressure;
+
+    pressure.pressure += (air_in - air_out) * dt;
+}
+
+fn calc_pitch_sag(
+    pressure: &PlenumPressure,
+    pitch_modulation_depth: f64,
+    max_pressure: f64,
+    midi_freq: f64,
+) -> f64 {
+    let sag = 1.0 - (pitch_modulation_depth * (max_pressure - pressure.pressure));
+    midi_freq * sag
+}
+
+fn synthesize_pulse_wave(voice_state: &mut RosedaleVoiceState, params: &RosedaleParams) -> f64 {
+    if voice_state.phase > params.pulse_duty_cycle {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+//The harmonic number each entry of `RosedaleParams::partial_gains` voices, e.g. a soft reed-organ rank
+const HARMONIC_NUMBERS: [u32; 5] = [1, 2, 3, 4, 7];
+
+fn synthesize_additive_wave(
+    voice_state: &mut RosedaleVoiceState,
+    params: &RosedaleParams,
+    freq: f64,
+    sample_rate: f64,
+) -> f64 {
+    let nyquist = sample_rate / 2.0;
+    let mut out = 0.0;
+
+    for (gain, harmonic) in params.partial_gains.iter().zip(HARMONIC_NUMBERS) {
+        if freq * harmonic as f64 > nyquist {
+            continue;
+        }
+        out += gain * (2.0 * PI * harmonic as f64 * voice_state.phase).sin();
+    }
+
+    out
+}
+
+fn apply_chassis_filter(voice_state: &mut RosedaleVoiceState, alpha: f64, sample: f64) -> f64 {
+    let prev_sample = voice_state.sample_history;
+
+    let next_sample = prev_sample + alpha * (sample - prev_sample);
+
+    voice_state.sample_history = next_sample;
+
+    next_sample
+}
+
+//A command sent from a frontend's control surface (standalone stdin, or a future host command) into the engine
+pub enum EngineCommand {
+    Arm(PathBuf),
+    Disarm,
+}
+
+//Receives mono frames from the render loop and forwards them to the writer thread
+//via an `rtrb` ring buffer, mirroring how MIDI messages reach the engine
+struct RecordSink {
+    producer: Producer<f32>,
+}
+
+impl RecordSink {
+    fn push_frame(&mut self, sample: f32) {
+        let _ = self.producer.push(sample);
+    }
+}
+
+//Streams 16-bit PCM mono WAV to disk on a dedicated thread so the render loop never blocks on I/O
+struct WavWriter {
+    writer: BufWriter<File>,
+    sample_count: u32,
+}
+
+impl WavWriter {
+    fn create(path: &PathBuf, sample_rate: u32) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("Creating WAV file {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        write_wav_header(&mut writer, sample_rate, 1, 0)?;
+        Ok(Self {
+            writer,
+            sample_count: 0,
+        })
+    }
+
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        let pcm = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        self.writer.write_all(&pcm.to_le_bytes())?;
+        self.sample_count += 1;
+        Ok(())
+    }
+
+    //Patches the RIFF and data chunk sizes now that the final sample count is known
+    fn finalize(mut self) -> Result<()> {
+        let data_bytes = self.sample_count * 2;
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&(36 + data_bytes).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&data_bytes.to_le_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn write_wav_header(
+    writer: &mut BufWriter<File>,
+    sample_rate: u32,
+    channels: u16,
+    data_bytes: u32,
+) -> Result<()> {
+    let bits_per_sample: u16 = 16;
+    let block_align = channels * (bits_per_sample / 8);
+    let byte_rate = sample_rate * block_align as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_bytes).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&bits_per_sample.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_bytes.to_le_bytes())?;
+    Ok(())
+}
+
+//Drains the ring buffer and writes samples to disk until the `RecordSink` producer is dropped
+fn spawn_wav_writer_thread(mut consumer: Consumer<f32>, path: PathBuf, sample_rate: u32) {
+    thread::spawn(move || {
+        let mut writer = match WavWriter::create(&path, sample_rate) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Failed to start WAV capture: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            match consumer.pop() {
+                Ok(sample) => {
+                    if let Err(e) = writer.write_sample(sample) {
+                        eprintln!("Failed to write WAV sample: {}", e);
+                        return;
+                    }
+                }
+                Err(_) if consumer.is_abandoned() => break,
+                Err(_) => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+
+        if let Err(e) = writer.finalize() {
+            eprintln!("Failed to finalize WAV capture: {}", e);
+        }
+    });
+}
+
+pub struct RosedaleEngine {
+    pub params: RosedaleParams,
+    //One independent plenum per entry in params.wind_sections, kept in sync by sync_plenums()
+    plenums: Vec<PlenumPressure>,
+    //Reused per-frame scratch space for per-section total_aperature, sized with `plenums`
+    section_total_aperature: Vec<f64>,
+    voices: Vec<RosedaleVoiceState>,
+    sample_rate: f64,
+    active_indices: Vec<usize>,
+    cc_map: Vec<(u8, CcTarget)>,
+    record_sink: Option<RecordSink>,
+    pending_cc: Vec<(u8, u8)>,
+    //Current pitch-bend position per MIDI channel, in cents
+    pitch_bend_cents: [f64; 16],
+    //Whether the sustain pedal (CC 64) is currently held down
+    sustain_down: bool,
+    //Voices that released while the pedal was down; closed once it lifts
+    pending_release: HashSet<usize>,
+}
+
+impl RosedaleEngine {
+    pub fn new(sample_rate: f64) -> Self {
+        let mut voices = Vec::with_capacity(128);
+        for i in 0..128 {
+            voices.push(RosedaleVoiceState::new(i));
+        }
+        let params = RosedaleParams::default();
+        let section_count = params.wind_sections.len().max(1);
+        Self {
+            params,
+            plenums: vec![PlenumPressure { pressure: 0.0 }; section_count],
+            section_total_aperature: vec![0.0; section_count],
+            voices,
+            sample_rate,
+            active_indices: Vec::with_capacity(128),
+            cc_map: DEFAULT_CC_MAP.to_vec(),
+            pending_cc: Vec::with_capacity(16),
+            pitch_bend_cents: [0.0; 16],
+            sustain_down: false,
+            pending_release: HashSet::new(),
+            record_sink: None,
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    //Lets a frontend change the sample rate the host provides (e.g. once the DAW reports it)
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
+    }
+
+    //Replaces the CC -> RosedaleParams wiring, letting a frontend remap controllers at runtime
+    pub fn set_cc_map(&mut self, cc_map: Vec<(u8, CcTarget)>) {
+        self.cc_map = cc_map;
+    }
+
+    //Resizes plenums/scratch space to match params.wind_sections, in case it changed at runtime
+    fn sync_plenums(&mut self) {
+        let target = self.params.wind_sections.len().max(1);
+        self.plenums.resize(target, PlenumPressure { pressure: 0.0 });
+        self.section_total_aperature.resize(target, 0.0);
+    }
+
+    pub fn handle_command(&mut self, cmd: EngineCommand) {
+        match cmd {
+            EngineCommand::Arm(path) => {
+                let (producer, consumer) = RingBuffer::<f32>::new(self.sample_rate as usize);
+                spawn_wav_writer_thread(consumer, path, self.sample_rate as u32);
+                self.record_sink = Some(RecordSink { producer });
+            }
+            EngineCommand::Disarm => {
+                self.record_sink = None;
+            }
+        }
+    }
+
+    pub fn handle_midi(&mut self, msg: MidiMessage) {
+        match msg {
+            MidiMessage::NoteOn(channel, note, vel) => {
+                let idx = u8::from(note) as usize;
+                let v = u8::from(vel) as f64 / 127.0;
+
+                if v > 0.0 {
+                    let section_count = self.params.wind_sections.len().max(1);
+                    self.voices[idx].opening = true;
+                    self.voices[idx].attack = v;
+                    self.voices[idx].channel = u8::from(channel);
+                    self.voices[idx].section = section_for_note(
+                        &self.params.section_routing,
+                        u8::from(channel),
+                        idx as u8,
+                        section_count,
+                    );
+                    self.pending_release.remove(&idx);
+
+                    if !self.active_indices.contains(&idx) {
+                        self.active_indices.push(idx);
+                    }
+                } else {
+                    self.voices[idx].opening = false;
+                }
+            }
+            MidiMessage::NoteOff(_, note, _) => {
+                let idx = u8::from(note) as usize;
+                if self.sustain_down {
+                    self.pending_release.insert(idx);
+                } else {
+                    self.voices[idx].opening = false;
+                }
+            }
+            MidiMessage::ControlChange(_, cc, value) if u8::from(cc) == SUSTAIN_CC => {
+                let down = u8::from(value) >= 64;
+                if down {
+                    self.sustain_down = true;
+                } else {
+                    self.sustain_down = false;
+                    for idx in self.pending_release.drain() {
+                        self.voices[idx].opening = false;
+                    }
+                }
+            }
+            MidiMessage::ControlChange(_, cc, value) => {
+                self.pending_cc.push((u8::from(cc), u8::from(value)));
+            }
+            MidiMessage::PitchBendChange(channel, bend) => {
+                //14-bit value is centered at 0x2000; map the signed deflection onto +/- bend range
+                let raw = u16::from(bend) as f64 - 8192.0;
+                let normalized = raw / 8192.0;
+                let cents = normalized * self.params.pitch_bend_range_semitones * 100.0;
+                self.pitch_bend_cents[u8::from(channel) as usize] = cents;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn process_buffer(&mut self, buffer: &mut [f32], channels: usize) {
+        for (cc, value) in self.pending_cc.drain(..) {
+            if let Some(&(_, target)) = self.cc_map.iter().find(|(mapped_cc, _)| *mapped_cc == cc)
+            {
+                apply_cc(&mut self.params, target, value);
+            }
+        }
+
+        self.sync_plenums();
+
+        let dt = 1.0 / self.sample_rate;
+        let alpha = calculate_alpha(self.params.filter_cutoff, dt);
+        for frame in buffer.chunks_mut(channels) {
+            for total in self.section_total_aperature.iter_mut() {
+                *total = 0.0;
+            }
+            for &i in &self.active_indices {
+                let voice = &self.voices[i];
+                //wind_sections may have shrunk since this voice's NoteOn assigned its section
+                let section = voice.section.min(self.section_total_aperature.len() - 1);
+                self.section_total_aperature[section] += voice.valve_aperature;
+            }
+            for (section_idx, section) in self.params.wind_sections.iter().enumerate() {
+                update_pressure(
+                    &mut self.plenums[section_idx],
+                    section.max_pressure,
+                    section.refill_speed,
+                    self.params.valve_flow_rate,
+                    self.section_total_aperature[section_idx],
+                    dt,
+                );
+            }
+
+            let mut mono_mix = 0.0;
+
+            let active_indices = &self.active_indices;
+            let voices = &mut self.voices;
+            let plenums = &mut self.plenums;
+            let params = &self.params;
+
+            for &i in active_indices {
+                let voice = &mut voices[i];
+                //wind_sections may have shrunk since this voice's NoteOn assigned its section
+                let section = voice.section.min(plenums.len() - 1);
+                let pressure = &mut plenums[section];
+                update_aperature(voice, pressure, params, dt);
+                update_envelope(voice, params, dt);
+
+                if voice.envelope_stage == EnvelopeStage::Idle {
+                    continue;
+                }
+
+                let section_max_pressure = params
+                    .wind_sections
+                    .get(section)
+                    .map(|s| s.max_pressure)
+                    .unwrap_or(1.0);
+                let sagged_freq = calc_pitch_sag(
+                    pressure,
+                    params.pitch_modulation_depth,
+                    section_max_pressure,
+                    voice.freq,
+                );
+                let bend_cents = self.pitch_bend_cents[voice.channel as usize];
+                let freq = sagged_freq * 2.0_f64.powf(bend_cents / 1200.0);
+
+                voice.phase += freq * dt;
+                if voice.phase > 1.0 {
+                    voice.phase -= 1.0;
+                }
+
+                let raw = match params.oscillator_mode {
+                    OscillatorMode::Pulse => synthesize_pulse_wave(voice, params),
+                    OscillatorMode::Additive => {
+                        synthesize_additive_wave(voice, params, freq, self.sample_rate)
+                    }
+                };
+                let filtered = apply_chassis_filter(voice, alpha, raw);
+
+                //Loudness comes from the ADSR envelope scaled by the section's plenum pressure;
+                //valve_aperature no longer scales amplitude directly
+                mono_mix += filtered * pressure.pressure * voice.envelope_level;
+            }
+
+            mono_mix = mono_mix.tanh();
+            if let Some(sink) = &mut self.record_sink {
+                sink.push_frame(mono_mix as f32);
+            }
+            for sample in frame {
+                *sample = mono_mix as f32;
+            }
+        }
+        let voices = &self.voices;
+        self.active_indices.retain(|&i| {
+            let v = &voices[i];
+            v.opening || v.envelope_stage != EnvelopeStage::Idle
+        });
+    }
+}
@@ -0,0 +1,288 @@
+//VST3/CLAP frontend over the same `RosedaleEngine` core the standalone binary uses.
+//Only compiled with `--features plugin`; everything host-specific (automatable params,
+//note/CC event translation, block rendering) lives here, not in `engine.rs`.
+#![cfg(feature = "plugin")]
+
+use std::sync::Arc;
+
+use nih_plug::prelude::*;
+use wmidi::{ControlFunction, MidiMessage, Note, PitchBend, U7, Velocity};
+
+use crate::engine::{OscillatorMode, RosedaleEngine};
+
+#[derive(Params)]
+struct RosedaleNihParams {
+    #[id = "max_pressure"]
+    max_pressure: FloatParam,
+    #[id = "refill_speed"]
+    refill_speed: FloatParam,
+    #[id = "filter_cutoff"]
+    filter_cutoff: FloatParam,
+    #[id = "spring_speed"]
+    spring_speed: FloatParam,
+    #[id = "pitch_mod_depth"]
+    pitch_modulation_depth: FloatParam,
+    #[id = "pitch_bend_range"]
+    pitch_bend_range_semitones: FloatParam,
+    #[id = "additive"]
+    additive_oscillator: BoolParam,
+    #[id = "attack_time"]
+    attack_time: FloatParam,
+    #[id = "decay_time"]
+    decay_time: FloatParam,
+    #[id = "sustain_level"]
+    sustain_level: FloatParam,
+    #[id = "release_time"]
+    release_time: FloatParam,
+}
+
+impl Default for RosedaleNihParams {
+    fn default() -> Self {
+        Self {
+            max_pressure: FloatParam::new(
+                "Max Pressure",
+                1.0,
+                FloatRange::Linear { min: 0.1, max: 2.0 },
+            ),
+            refill_speed: FloatParam::new(
+                "Refill Speed",
+                10.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 40.0,
+                },
+            ),
+            filter_cutoff: FloatParam::new(
+                "Filter Cutoff",
+                1500.0,
+                FloatRange::Skewed {
+                    min: 200.0,
+                    max: 8000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz"),
+            spring_speed: FloatParam::new(
+                "Spring Speed",
+                25.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 80.0,
+                },
+            ),
+            pitch_modulation_depth: FloatParam::new(
+                "Pitch Modulation Depth",
+                0.06,
+                FloatRange::Linear { min: 0.0, max: 0.3 },
+            ),
+            pitch_bend_range_semitones: FloatParam::new(
+                "Pitch Bend Range",
+                2.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" st"),
+            additive_oscillator: BoolParam::new("Additive Oscillator", false),
+            attack_time: FloatParam::new(
+                "Attack",
+                0.05,
+                FloatRange::Linear {
+                    min: 0.001,
+                    max: 2.0,
+                },
+            )
+            .with_unit(" s"),
+            decay_time: FloatParam::new(
+                "Decay",
+                0.2,
+                FloatRange::Linear {
+                    min: 0.001,
+                    max: 2.0,
+                },
+            )
+            .with_unit(" s"),
+            sustain_level: FloatParam::new(
+                "Sustain",
+                0.8,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            release_time: FloatParam::new(
+                "Release",
+                0.3,
+                FloatRange::Linear {
+                    min: 0.001,
+                    max: 4.0,
+                },
+            )
+            .with_unit(" s"),
+        }
+    }
+}
+
+pub struct RosedalePlugin {
+    params: Arc<RosedaleNihParams>,
+    engine: RosedaleEngine,
+}
+
+impl Default for RosedalePlugin {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(RosedaleNihParams::default()),
+            engine: RosedaleEngine::new(44100.0),
+        }
+    }
+}
+
+//Pulls the current value of every automatable host parameter into the engine's own
+//RosedaleParams, mirroring how MIDI CCs are applied at the start of process_buffer
+fn sync_params(engine: &mut RosedaleEngine, params: &RosedaleNihParams) {
+    let p = &mut engine.params;
+    //The plugin only automates the primary wind section; any further sections keep their RosedaleParams values
+    if let Some(section) = p.wind_sections.get_mut(0) {
+        section.max_pressure = params.max_pressure.value() as f64;
+        section.refill_speed = params.refill_speed.value() as f64;
+    }
+    p.filter_cutoff = params.filter_cutoff.value() as f64;
+    p.spring_speed = params.spring_speed.value() as f64;
+    p.pitch_modulation_depth = params.pitch_modulation_depth.value() as f64;
+    p.pitch_bend_range_semitones = params.pitch_bend_range_semitones.value() as f64;
+    p.oscillator_mode = if params.additive_oscillator.value() {
+        OscillatorMode::Additive
+    } else {
+        OscillatorMode::Pulse
+    };
+    p.attack_time = params.attack_time.value() as f64;
+    p.decay_time = params.decay_time.value() as f64;
+    p.sustain_level = params.sustain_level.value() as f64;
+    p.release_time = params.release_time.value() as f64;
+}
+
+impl Plugin for RosedalePlugin {
+    const NAME: &'static str = "Rosedale";
+    const VENDOR: &'static str = "caminante-blanco";
+    const URL: &'static str = "https://github.com/caminante-blanco/rosedale";
+    const EMAIL: &'static str = "info@example.com";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: None,
+        main_output_channels: NonZeroU32::new(1),
+        ..AudioIOLayout::const_default()
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.engine
+            .set_sample_rate(buffer_config.sample_rate as f64);
+        true
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        sync_params(&mut self.engine, &self.params);
+
+        while let Some(event) = context.next_event() {
+            if let Some(msg) = note_event_to_midi(&event) {
+                self.engine.handle_midi(msg);
+            }
+        }
+
+        //The engine renders a flat mono f32 slice; host buffers give us per-channel slices
+        let mut scratch = vec![0.0f32; buffer.samples()];
+        self.engine.process_buffer(&mut scratch, 1);
+
+        for (sample_idx, channel_samples) in buffer.iter_samples().enumerate() {
+            for sample in channel_samples {
+                *sample = scratch[sample_idx];
+            }
+        }
+
+        ProcessStatus::Normal
+    }
+}
+
+//Translates a host NoteEvent into the wmidi::MidiMessage shape `RosedaleEngine::handle_midi` expects
+fn note_event_to_midi(event: &NoteEvent<()>) -> Option<MidiMessage<'static>> {
+    match *event {
+        NoteEvent::NoteOn {
+            channel,
+            note,
+            velocity,
+            ..
+        } => Some(MidiMessage::NoteOn(
+            channel_from_u8(channel),
+            Note::from_u8_lossy(note),
+            velocity_from_f32(velocity),
+        )),
+        NoteEvent::NoteOff {
+            channel,
+            note,
+            velocity,
+            ..
+        } => Some(MidiMessage::NoteOff(
+            channel_from_u8(channel),
+            Note::from_u8_lossy(note),
+            velocity_from_f32(velocity),
+        )),
+        NoteEvent::MidiCC {
+            channel, cc, value, ..
+        } => Some(MidiMessage::ControlChange(
+            channel_from_u8(channel),
+            ControlFunction(U7::from_u8_lossy(cc)),
+            U7::from_u8_lossy((value * 127.0).round() as u8),
+        )),
+        NoteEvent::MidiPitchBend { channel, value, .. } => Some(MidiMessage::PitchBendChange(
+            channel_from_u8(channel),
+            PitchBend::from_u16_lossy((value * 16383.0).round() as u16),
+        )),
+        _ => None,
+    }
+}
+
+fn channel_from_u8(channel: u8) -> wmidi::Channel {
+    wmidi::Channel::from_index(channel).unwrap_or(wmidi::Channel::Ch1)
+}
+
+fn velocity_from_f32(velocity: f32) -> Velocity {
+    U7::from_u8_lossy((velocity * 127.0).round() as u8)
+}
+
+impl ClapPlugin for RosedalePlugin {
+    const CLAP_ID: &'static str = "com.caminante-blanco.rosedale";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("A bellows-and-valve physical model synth");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    const CLAP_FEATURES: &'static [ClapFeature] =
+        &[ClapFeature::Instrument, ClapFeature::Synthesizer, ClapFeature::Mono];
+}
+
+impl Vst3Plugin for RosedalePlugin {
+    const VST3_CLASS_ID: [u8; 16] = *b"RosedaleSynthV3!";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Instrument, Vst3SubCategory::Synth];
+}
+
+nih_export_clap!(RosedalePlugin);
+nih_export_vst3!(RosedalePlugin);
@@ -0,0 +1,98 @@
+//The default frontend: opens a cpal output stream and a midir virtual MIDI port, and
+//drives `RosedaleEngine` from them. See `plugin.rs` for the other frontend over the same core.
+use std::io::stdin;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Stream, StreamConfig, default_host};
+use midir::{MidiInput, MidiInputConnection, os::unix::VirtualInput};
+use rtrb::{Consumer, Producer, RingBuffer};
+use wmidi::MidiMessage;
+
+use crate::engine::{EngineCommand, RosedaleEngine};
+
+struct RosedaleSynth {
+    _stream: Stream,
+}
+
+impl RosedaleSynth {
+    fn new(
+        mut midi_consumer: Consumer<MidiMessage<'static>>,
+        mut command_consumer: Consumer<EngineCommand>,
+    ) -> Result<Self> {
+        let host = default_host();
+        let device = host
+            .default_output_device()
+            .context("No audio output device found")?;
+        let config: StreamConfig = device.default_output_config()?.into();
+        let mut engine = RosedaleEngine::new(config.sample_rate.0 as f64);
+        let channels = config.channels as usize;
+
+        let stream = device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                while let Ok(msg) = midi_consumer.pop() {
+                    engine.handle_midi(msg);
+                }
+                while let Ok(cmd) = command_consumer.pop() {
+                    engine.handle_command(cmd);
+                }
+                engine.process_buffer(data, channels);
+            },
+            |err| eprint!("Audio stream error: {}", err),
+            None,
+        )?;
+        stream.play()?;
+        Ok(Self { _stream: stream })
+    }
+}
+
+fn connect_to_midi(
+    mut producer: Producer<MidiMessage<'static>>,
+) -> Result<MidiInputConnection<()>> {
+    let mut midi_input = MidiInput::new("Rosedale Synth")?;
+
+    midi_input.ignore(midir::Ignore::TimeAndActiveSense);
+
+    let midi_processor = move |_timestamp: u64, raw_bytes: &[u8], _: &mut ()| {
+        if let Ok(msg) = MidiMessage::try_from(raw_bytes) {
+            let _ = producer.push(msg.to_owned());
+        }
+    };
+
+    let conn = midi_input
+        .create_virtual("Rosedale Port", midi_processor, ())
+        .map_err(|e| anyhow::anyhow!("Error creating MIDI virtual port: {}", e))?;
+
+    Ok(conn)
+}
+
+pub fn run() -> Result<()> {
+    let (producer, consumer) = RingBuffer::<MidiMessage<'static>>::new(128);
+
+    let _midi_conn = connect_to_midi(producer).context("Failed to connect to MIDI");
+
+    let (mut command_producer, command_consumer) = RingBuffer::<EngineCommand>::new(16);
+
+    let _synth =
+        RosedaleSynth::new(consumer, command_consumer).context("Audio Stream failed to start");
+
+    println!("Commands: `record <path.wav>` to start capture, `stop` to stop, empty line to exit.");
+    loop {
+        let mut input = String::new();
+        stdin().read_line(&mut input)?;
+        let command = input.trim();
+
+        if command.is_empty() {
+            break;
+        } else if let Some(path) = command.strip_prefix("record ") {
+            let _ = command_producer.push(EngineCommand::Arm(PathBuf::from(path.trim())));
+        } else if command == "stop" {
+            let _ = command_producer.push(EngineCommand::Disarm);
+        } else {
+            println!("Unknown command: {}", command);
+        }
+    }
+    Ok(())
+}